@@ -50,7 +50,17 @@ fn main() {
         dotopts = dotopts.stale_age(stale);
     }
     let mut lock = dotopts.create(&opts.lockfile).unwrap_or_else(|err| {
-            println!("dotlock: Fatal error: {}", err);
+            match err {
+                DotlockError::Locked { ref path, attempts, .. } => {
+                    println!(
+                        "dotlock: could not lock {} after {} attempts; {} may need manual removal",
+                        path.display(),
+                        attempts,
+                        path.display(),
+                    );
+                }
+                DotlockError::Io(_) => println!("dotlock: Fatal error: {}", err),
+            }
             exit(111);
         });
     writeln!(lock, "Don't touch this!").unwrap();
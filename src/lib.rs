@@ -9,8 +9,8 @@
 //! the same as the file they are referencing with the extension of
 //! `.lock`.
 //!
-//! The algorithm that is used to create a lock file in an atomic way is
-//! as follows:
+//! The algorithm used to create a lock file in an atomic way is
+//! platform-specific. On Unix (including NFS):
 //!
 //! 1. A unique file is created using
 //! [`tempfile`](https://docs.rs/tempfile).
@@ -29,27 +29,184 @@
 //! are compared. If they are the same file, then we have successfully
 //! locked the file. Return the opened file.
 //!
-//! 6. If the lock file is stale (older than a configured age), delete
-//! the existing lock file.
+//! On Windows, which has no equivalent of the hard-link + inode trick,
+//! the destination is instead opened with `create_new`, which
+//! atomically fails if the file already exists.
+//!
+//! On both platforms, after a failed attempt:
+//!
+//! 6. If `pid_check` is enabled and the lock file names a PID on this
+//! host that is no longer running, delete it. Otherwise, if the lock
+//! file is stale (older than a configured age), delete it.
 //!
 //! 7. Before retrying, sleep briefly (defaults to 5 seconds).
 
+extern crate filetime;
+extern crate libc;
+extern crate once_cell;
+extern crate parking_lot;
 extern crate tempfile;
 
+mod platform;
+
+use std::collections::HashMap;
+use std::fmt;
 use std::fs::{remove_file, File, Metadata, Permissions};
 use std::io::{Error, ErrorKind, Read, Result, Seek, SeekFrom, Write};
-use std::os::linux::fs::MetadataExt;
 use std::path::{Path, PathBuf};
-use std::thread::sleep;
-use std::time::{Duration, SystemTime};
-use tempfile::Builder;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::thread::{self, sleep};
+use std::time::{Duration, Instant, SystemTime};
+use filetime::{set_file_mtime, FileTime};
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+
+// Per-path mutexes used to serialize the brief create/link/compare
+// race in `platform::try_acquire` across threads of this process, so
+// they don't needlessly race each other over the filesystem. Not held
+// for the lifetime of a `Dotlock`/`Marker` -- see `path_mutex`.
+static PATH_LOCKS: OnceCell<Mutex<HashMap<PathBuf, Weak<Mutex<()>>>>> = OnceCell::new();
+
+// Returns the (possibly newly created) mutex guarding in-process
+// access to `path` for the duration of a single `platform::try_acquire`
+// call, which should already be canonicalized so that different
+// spellings of the same path share one mutex.
+fn path_mutex(path: &Path) -> Arc<Mutex<()>> {
+    let registry = PATH_LOCKS.get_or_init(|| Mutex::new(HashMap::new()));
+    let mut registry = registry.lock();
+    if let Some(existing) = registry.get(path).and_then(Weak::upgrade) {
+        return existing;
+    }
+    let fresh = Arc::new(Mutex::new(()));
+    registry.insert(path.to_path_buf(), Arc::downgrade(&fresh));
+    fresh
+}
+
+// Canonicalizes `path` for use as a `path_mutex` key. The lock file
+// itself may not exist yet, so only its parent directory is resolved;
+// if that fails too (e.g. it doesn't exist either), `path` is used
+// as-is, which still works correctly, just without de-duplicating
+// differently-spelled paths to the same file.
+fn canonical_lock_path(path: &Path) -> PathBuf {
+    let parent = path.parent().unwrap_or_else(|| Path::new("."));
+    match std::fs::canonicalize(parent) {
+        Ok(mut canon) => {
+            if let Some(name) = path.file_name() {
+                canon.push(name);
+            }
+            canon
+        }
+        Err(_) => path.to_path_buf(),
+    }
+}
 
 const DEFAULT_PAUSE: Duration = Duration::from_secs(5);
 const DEFAULT_TRIES: usize = 10;
+const MIN_BACKOFF: Duration = Duration::from_millis(1);
+const MAX_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Controls how [`Dotlock::create`] retries after an attempt fails
+/// because the lock file already exists. A stale lock file is removed
+/// after the failed attempt, and retaking it also uses a retry.
+///
+/// [`Dotlock::create`]: struct.Dotlock.html#method.create
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Retry {
+    /// Make a single attempt and give up immediately if it fails.
+    Immediately,
+    /// Retry a fixed number of times, pausing a constant duration
+    /// between each attempt. This is the default, and mirrors the
+    /// behavior of the original `pause`/`tries` options.
+    Fixed { pause: Duration, tries: usize },
+    /// Keep retrying with exponentially increasing pauses (starting
+    /// small and capped at a short ceiling) until `until` has elapsed
+    /// since the first attempt, rather than giving up after a fixed
+    /// number of tries.
+    Backoff { until: Duration },
+}
+
+/// The error type returned when creating a [`Dotlock`] fails.
+///
+/// [`Dotlock`]: struct.Dotlock.html
+#[derive(Debug)]
+pub enum DotlockError {
+    /// An I/O operation failed while attempting to create the lock
+    /// file.
+    Io(Error),
+    /// The lock file could not be acquired after exhausting the
+    /// configured [`Retry`] strategy.
+    ///
+    /// [`Retry`]: enum.Retry.html
+    Locked {
+        /// The lock file that could not be created.
+        path: PathBuf,
+        /// The number of attempts made before giving up.
+        attempts: usize,
+        /// The retry strategy that was used.
+        retry: Retry,
+    },
+}
+
+impl fmt::Display for DotlockError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DotlockError::Io(err) => err.fmt(f),
+            DotlockError::Locked { path, attempts, .. } => write!(
+                f,
+                "could not lock {} after {} attempt{}; {} may need manual removal",
+                path.display(),
+                attempts,
+                if *attempts == 1 { "" } else { "s" },
+                path.display(),
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DotlockError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DotlockError::Io(err) => Some(err),
+            DotlockError::Locked { .. } => None,
+        }
+    }
+}
 
-// Do the two Metadata reference the same file?
-fn meta_eq(a: &Metadata, b: &Metadata) -> bool {
-    a.st_dev() == b.st_dev() && a.st_ino() == b.st_ino()
+impl From<Error> for DotlockError {
+    fn from(err: Error) -> Self {
+        DotlockError::Io(err)
+    }
+}
+
+impl From<DotlockError> for Error {
+    fn from(err: DotlockError) -> Self {
+        match err {
+            DotlockError::Io(err) => err,
+            DotlockError::Locked { .. } => Error::new(ErrorKind::TimedOut, err.to_string()),
+        }
+    }
+}
+
+// Writes the "<pid> <hostname>" line that `pid_check` uses to later tell
+// whether the process that created a lock file is still alive.
+pub(crate) fn write_owner_line(file: &File) -> Result<()> {
+    if let Some(host) = platform::hostname() {
+        let mut file = file;
+        write!(file, "{} {}\n", std::process::id(), host)?;
+    }
+    Ok(())
+}
+
+// Reads back a "<pid> <hostname>" line previously written by
+// `write_owner_line`. Returns `None` if the file is missing or doesn't
+// look like one we wrote.
+fn read_owner_line(path: &Path) -> Option<(i32, String)> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    let mut parts = contents.trim().splitn(2, ' ');
+    let pid = parts.next()?.parse().ok()?;
+    let host = parts.next()?.to_owned();
+    Some((pid, host))
 }
 
 /// A created ".lock" file.
@@ -57,68 +214,142 @@ fn meta_eq(a: &Metadata, b: &Metadata) -> bool {
 pub struct Dotlock {
     file: File,
     path: Option<PathBuf>,
+    heartbeat_stop: Option<Arc<AtomicBool>>,
 }
 
 impl Dotlock {
-    fn create_in(path: &Path, options: DotlockOptions, tempdir: &Path) -> Result<File> {
-        for trynum in 0..options.tries {
+    fn create_in(path: &Path, options: &DotlockOptions, tempdir: &Path) -> std::result::Result<File, DotlockError> {
+        let start = Instant::now();
+        let mut backoff = MIN_BACKOFF;
+        let mut trynum = 0;
+        let guard_mutex = path_mutex(&canonical_lock_path(path));
+        loop {
             if trynum > 0 {
-                // Pause only before retrying
-                sleep(options.pause);
+                // Pause only before retrying, and decide whether this
+                // retry is allowed at all.
+                match options.retry {
+                    Retry::Immediately => break,
+                    Retry::Fixed { tries, .. } if trynum >= tries => break,
+                    Retry::Backoff { until } if start.elapsed() >= until => break,
+                    Retry::Fixed { pause, .. } => sleep(pause),
+                    Retry::Backoff { .. } => {
+                        sleep(backoff);
+                        backoff = (backoff * 2).min(MAX_BACKOFF);
+                    }
+                }
             }
-            // Create a unique temporary file in the same directory
-            let temp = Builder::new().tempfile_in(tempdir)?;
-            let tempmeta = temp.as_file().metadata()?;
-            // link temporary file to destination, ignore the result
-            std::fs::hard_link(temp.path(), &path).ok();
-            // Drop the temporary file
-            let temp = temp.into_file();
-            // stat the destination lock file
+            trynum += 1;
+            // Hold the in-process mutex only for the create/link/compare
+            // race itself, not for the lifetime of the lock, so a
+            // second attempt on the same path -- even from this same
+            // thread -- never blocks here.
+            let acquired = {
+                let _guard = guard_mutex.lock();
+                platform::try_acquire(path, tempdir, options.pid_check)?
+            };
+            if let Some(file) = acquired {
+                if let Some(perm) = &options.permissions {
+                    file.set_permissions(perm.clone())?;
+                }
+                return Ok(file);
+            }
+            // Someone else holds the lock; see if it can be broken.
             let destmeta = match std::fs::metadata(&path) {
                 Ok(meta) => meta,
                 Err(_) => continue,
             };
-            // Compare result of stat to temporary file
-            if meta_eq(&destmeta, &tempmeta) {
-                if let Some(perm) = options.permissions {
-                    temp.set_permissions(perm)?;
+            // Is the existing lock stale because the process that
+            // created it has died?
+            let mut removed = false;
+            if options.pid_check {
+                if let Some((pid, host)) = read_owner_line(&path) {
+                    if platform::hostname().as_deref() == Some(host.as_str()) && !platform::process_alive(pid) {
+                        remove_file(&path).ok();
+                        removed = true;
+                    }
                 }
-                return Ok(temp);
             }
-            // Is the existing lock stale?
-            if let Some(stale_age) = options.stale_age {
-                let now = SystemTime::now();
-                if let Ok(modtime) = destmeta.modified() {
-                    if let Ok(age) = now.duration_since(modtime) {
-                        if age < stale_age {
-                            remove_file(&path).ok();
+            // Otherwise, is the existing lock stale by mtime?
+            if !removed {
+                if let Some(stale_age) = options.stale_age {
+                    let now = SystemTime::now();
+                    if let Ok(modtime) = destmeta.modified() {
+                        if let Ok(age) = now.duration_since(modtime) {
+                            if age > stale_age {
+                                remove_file(&path).ok();
+                            }
                         }
                     }
                 }
             }
         }
-        Err(Error::new(ErrorKind::TimedOut, "Timed out"))
+        Err(DotlockError::Locked {
+            path: path.to_path_buf(),
+            attempts: trynum,
+            retry: options.retry,
+        })
     }
 
-    fn create_with(path: PathBuf, options: DotlockOptions) -> Result<Self> {
-        let file = Self::create_in(&path, options, &path.parent().unwrap_or(Path::new(".")))?;
+    fn create_with(path: PathBuf, options: DotlockOptions) -> std::result::Result<Self, DotlockError> {
+        let file = Self::create_in(&path, &options, &path.parent().unwrap_or(Path::new(".")))?;
+        let heartbeat_stop = match (options.heartbeat, options.stale_age) {
+            (true, Some(stale_age)) => Some(Self::spawn_heartbeat(path.clone(), stale_age)),
+            _ => None,
+        };
         Ok(Self {
             file,
             path: Some(path),
+            heartbeat_stop,
         })
     }
 
+    // Spawns a background thread that periodically bumps the lock
+    // file's mtime so a long-running holder isn't broken by another
+    // process's `stale_age` check. The thread exits once `stop` is set.
+    fn spawn_heartbeat(path: PathBuf, stale_age: Duration) -> Arc<AtomicBool> {
+        let stop = Arc::new(AtomicBool::new(false));
+        let interval = stale_age / 3;
+        let thread_stop = stop.clone();
+        thread::spawn(move || {
+            while !thread_stop.load(Ordering::Relaxed) {
+                sleep(interval);
+                if thread_stop.load(Ordering::Relaxed) {
+                    break;
+                }
+                set_file_mtime(&path, FileTime::now()).ok();
+            }
+        });
+        stop
+    }
+
     /// Attempts to create the named lock file using the default options.
-    pub fn create<T: Into<PathBuf>>(path: T) -> Result<Self> {
+    pub fn create<T: Into<PathBuf>>(path: T) -> std::result::Result<Self, DotlockError> {
         DotlockOptions::new().create(path.into())
     }
 
     /// Unlocks the lock by removing the file. The lock will be
     /// automatically removed when this `Dotlock` is dropped.
     pub fn unlock(&mut self) -> Result<()> {
+        if let Some(stop) = self.heartbeat_stop.take() {
+            stop.store(true, Ordering::Relaxed);
+        }
         self.path.take().map_or(Ok(()), |path| remove_file(path))
     }
 
+    /// Updates the lock file's modification time to the current time.
+    /// Call this periodically while a lock is held across an operation
+    /// that may outlast `stale_age`, so another process doesn't break
+    /// the lock out from under you. See also [`DotlockOptions::heartbeat`]
+    /// for a background thread that does this automatically.
+    ///
+    /// [`DotlockOptions::heartbeat`]: struct.DotlockOptions.html#method.heartbeat
+    pub fn touch(&self) -> Result<()> {
+        match &self.path {
+            Some(path) => set_file_mtime(path, FileTime::now()),
+            None => Ok(()),
+        }
+    }
+
     /// Attempts to sync all OS-internal metadata to disk. Calls
     /// [`File::sync_all`](https://doc.rust-lang.org/std/fs/struct.File.html#method.sync_all).
     pub fn sync_all(&self) -> Result<()> {
@@ -150,6 +381,17 @@ impl Dotlock {
     pub fn set_permissions(&self, perm: Permissions) -> Result<()> {
         self.file.set_permissions(perm)
     }
+
+    /// Attempts to acquire the lock at `path` in a single, non-blocking
+    /// attempt, without returning a writable file handle. See
+    /// [`Marker`] for details, and [`DotlockOptions::try_lock_marker`]
+    /// to configure `stale_age` or `pid_check` first.
+    ///
+    /// [`Marker`]: struct.Marker.html
+    /// [`DotlockOptions::try_lock_marker`]: struct.DotlockOptions.html#method.try_lock_marker
+    pub fn try_lock_marker<T: Into<PathBuf>>(path: T) -> std::result::Result<Marker, DotlockError> {
+        DotlockOptions::new().try_lock_marker(path)
+    }
 }
 
 impl Drop for Dotlock {
@@ -179,6 +421,44 @@ impl Write for Dotlock {
     }
 }
 
+/// A lightweight lock acquired in a single, non-blocking attempt,
+/// which does not hold an open file handle for its lifetime.
+///
+/// This serves callers who only need mutual exclusion over a resource
+/// and never write to the lock file, or who want to probe "is this
+/// locked right now?" without paying for the retry loop that
+/// [`Dotlock::create`] uses. The lock file is removed when the
+/// `Marker` is dropped.
+///
+/// [`Dotlock::create`]: struct.Dotlock.html#method.create
+#[derive(Debug)]
+pub struct Marker {
+    path: PathBuf,
+}
+
+impl Marker {
+    /// Attempts to acquire the lock at `path` in a single, non-blocking
+    /// attempt, using the default options otherwise. See
+    /// [`DotlockOptions::try_lock_marker`] to configure `stale_age` or
+    /// `pid_check` first.
+    ///
+    /// [`DotlockOptions::try_lock_marker`]: struct.DotlockOptions.html#method.try_lock_marker
+    pub fn try_lock<T: Into<PathBuf>>(path: T) -> std::result::Result<Self, DotlockError> {
+        DotlockOptions::new().try_lock_marker(path)
+    }
+
+    /// The path of the lock file this marker holds.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for Marker {
+    fn drop(&mut self) {
+        remove_file(&self.path).ok();
+    }
+}
+
 /// Options which can be used to configure how a lock file is created.
 ///
 /// This builder exposes the ability to configure how a lock file is
@@ -216,34 +496,65 @@ impl Write for Dotlock {
 /// ```
 #[derive(Debug)]
 pub struct DotlockOptions {
-    pause: Duration,
-    tries: usize,
+    retry: Retry,
     permissions: Option<Permissions>,
     stale_age: Option<Duration>,
+    pid_check: bool,
+    heartbeat: bool,
 }
 
 impl DotlockOptions {
     /// Create a new set of options.
     pub fn new() -> Self {
         Self {
-            pause: DEFAULT_PAUSE,
-            tries: DEFAULT_TRIES,
+            retry: Retry::Fixed {
+                pause: DEFAULT_PAUSE,
+                tries: DEFAULT_TRIES,
+            },
             permissions: None,
             stale_age: None,
+            pid_check: false,
+            heartbeat: false,
         }
     }
 
     /// Set the time `Dotlock` will pause between attempts to create the
-    /// lock file. Defaults to 5 seconds.
+    /// lock file. Defaults to 5 seconds. This is shorthand for setting
+    /// [`retry`] to [`Retry::Fixed`] while keeping the current `tries`.
+    ///
+    /// [`retry`]: #method.retry
+    /// [`Retry::Fixed`]: enum.Retry.html#variant.Fixed
     pub fn pause<T: Into<Duration>>(mut self, pause: T) -> Self {
-        self.pause = pause.into();
+        let pause = pause.into();
+        self.retry = match self.retry {
+            Retry::Fixed { tries, .. } => Retry::Fixed { pause, tries },
+            _ => Retry::Fixed { pause, tries: DEFAULT_TRIES },
+        };
         self
     }
 
     /// Set the number of times `Dotlock` will try to create the lock
-    /// file. Defaults to 10 times.
+    /// file. Defaults to 10 times. This is shorthand for setting
+    /// [`retry`] to [`Retry::Fixed`] while keeping the current `pause`.
+    ///
+    /// [`retry`]: #method.retry
+    /// [`Retry::Fixed`]: enum.Retry.html#variant.Fixed
     pub fn tries(mut self, tries: usize) -> Self {
-        self.tries = tries.max(1);
+        let tries = tries.max(1);
+        self.retry = match self.retry {
+            Retry::Fixed { pause, .. } => Retry::Fixed { pause, tries },
+            _ => Retry::Fixed { pause: DEFAULT_PAUSE, tries },
+        };
+        self
+    }
+
+    /// Set the retry strategy used when an existing lock file prevents
+    /// immediate acquisition. Defaults to [`Retry::Fixed`] with a 5
+    /// second pause and 10 tries.
+    ///
+    /// [`Retry::Fixed`]: enum.Retry.html#variant.Fixed
+    pub fn retry(mut self, retry: Retry) -> Self {
+        self.retry = retry;
         self
     }
 
@@ -261,10 +572,54 @@ impl DotlockOptions {
         self
     }
 
+    /// Write the creating process's PID and hostname into the lock
+    /// file, and use them to detect staleness. If enabled, a lock file
+    /// left behind by a process that no longer exists on this host is
+    /// considered stale immediately, regardless of `stale_age`. If the
+    /// owner can't be determined (wrong format, or held by another
+    /// host), this falls back to the `stale_age` rule. Defaults to
+    /// `false`.
+    pub fn pid_check(mut self, enable: bool) -> Self {
+        self.pid_check = enable;
+        self
+    }
+
+    /// Spawn a background thread that refreshes the lock file's mtime
+    /// every `stale_age / 3` while the lock is held, so a legitimately
+    /// long-running holder doesn't have its lock broken by another
+    /// process's `stale_age` check. Has no effect unless `stale_age` is
+    /// also set. Defaults to `false`; see also [`Dotlock::touch`] for
+    /// refreshing it manually instead.
+    ///
+    /// [`Dotlock::touch`]: struct.Dotlock.html#method.touch
+    pub fn heartbeat(mut self, enable: bool) -> Self {
+        self.heartbeat = enable;
+        self
+    }
+
     /// Create the lock file at `path` with the options in `self`.
-    pub fn create<T: Into<PathBuf>>(self, path: T) -> Result<Dotlock> {
+    pub fn create<T: Into<PathBuf>>(self, path: T) -> std::result::Result<Dotlock, DotlockError> {
         Dotlock::create_with(path.into(), self)
     }
+
+    /// Attempt to acquire the lock file at `path` in a single attempt,
+    /// returning a [`Marker`] rather than a [`Dotlock`]: no writable
+    /// file handle is kept open for its lifetime. The `retry` setting
+    /// in `self` is ignored. If the `stale_age` or `pid_check` settings
+    /// find the existing lock file stale, it is removed, but this call
+    /// still fails; a later attempt can then acquire the lock.
+    ///
+    /// [`Marker`]: struct.Marker.html
+    /// [`Dotlock`]: struct.Dotlock.html
+    pub fn try_lock_marker<T: Into<PathBuf>>(self, path: T) -> std::result::Result<Marker, DotlockError> {
+        let path = path.into();
+        let options = DotlockOptions {
+            retry: Retry::Immediately,
+            ..self
+        };
+        Dotlock::create_in(&path, &options, &path.parent().unwrap_or(Path::new(".")))?;
+        Ok(Marker { path })
+    }
 }
 
 #[cfg(test)]
@@ -314,4 +669,104 @@ mod tests {
         let lock3 = DotlockOptions::new().stale_age(Duration::from_secs(1)).create(lockfile);
         assert!(lock3.is_ok());
     }
+
+    #[test]
+    fn retry_modes() {
+        let lockfile = "dotlock-retry-modes.lock";
+        let _lock = Dotlock::create(lockfile);
+        assert!(exists(lockfile));
+
+        let immediate = DotlockOptions::new().retry(Retry::Immediately).create(lockfile);
+        assert!(immediate.is_err());
+
+        let backoff = DotlockOptions::new()
+            .retry(Retry::Backoff { until: Duration::from_millis(50) })
+            .create(lockfile);
+        assert!(backoff.is_err());
+    }
+
+    #[test]
+    fn pid_check_removes_dead_owner() {
+        let lockfile = "dotlock-pid-check.lock";
+        let host = platform::hostname().unwrap();
+        // A PID that is vanishingly unlikely to be in use.
+        std::fs::write(lockfile, format!("999999999 {}\n", host)).unwrap();
+
+        let lock = DotlockOptions::new()
+            .tries(2)
+            .pause(Duration::from_millis(1))
+            .pid_check(true)
+            .create(lockfile);
+        assert!(lock.is_ok());
+    }
+
+    #[test]
+    fn touch_refreshes_mtime() {
+        let lockfile = "dotlock-touch.lock";
+        let lock = Dotlock::create(lockfile).unwrap();
+        let before = metadata(lockfile).unwrap().modified().unwrap();
+
+        sleep(Duration::from_millis(1100));
+        assert!(lock.touch().is_ok());
+
+        let after = metadata(lockfile).unwrap().modified().unwrap();
+        assert!(after > before);
+    }
+
+    #[test]
+    fn in_process_contention_is_serialized() {
+        let lockfile = "dotlock-in-process.lock";
+        let lock = Dotlock::create(lockfile).unwrap();
+
+        let waiter = std::thread::spawn(move || {
+            DotlockOptions::new()
+                .retry(Retry::Backoff { until: Duration::from_millis(500) })
+                .create(lockfile)
+        });
+        sleep(Duration::from_millis(50));
+        drop(lock);
+
+        let second = waiter.join().unwrap();
+        assert!(second.is_ok());
+    }
+
+    #[test]
+    fn marker_locks_without_a_file_handle() {
+        let lockfile = "dotlock-marker.lock";
+        let marker = Marker::try_lock(lockfile).unwrap();
+        assert_eq!(marker.path(), Path::new(lockfile));
+        assert!(exists(lockfile));
+
+        let other = Marker::try_lock(lockfile);
+        assert!(other.is_err());
+
+        drop(marker);
+        assert!(!exists(lockfile));
+    }
+
+    #[test]
+    fn heartbeat_keeps_lock_fresh() {
+        let lockfile = "dotlock-heartbeat.lock";
+        let lock = DotlockOptions::new()
+            .stale_age(Duration::from_millis(100))
+            .heartbeat(true)
+            .create(lockfile)
+            .unwrap();
+
+        // Long enough for several heartbeats, and well past stale_age.
+        sleep(Duration::from_millis(500));
+
+        // Several tries with a real pause between them: if the
+        // staleness check wrongly removed the lock file on an earlier
+        // attempt, a later attempt would succeed in recreating it, so
+        // this assertion actually exercises the heartbeat rather than
+        // passing no matter what the staleness check does.
+        let other = DotlockOptions::new()
+            .tries(5)
+            .pause(Duration::from_millis(50))
+            .stale_age(Duration::from_millis(100))
+            .create(lockfile);
+        assert!(other.is_err());
+        drop(lock);
+    }
 }
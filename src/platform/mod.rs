@@ -0,0 +1,17 @@
+//! Platform-specific pieces of the locking algorithm.
+//!
+//! The hard-link trick used on Unix (see the crate-level docs) has no
+//! equivalent on Windows, so each platform gets its own
+//! [`try_acquire`], [`hostname`] and [`process_alive`]. Everything else
+//! in the crate is written against this module's API and doesn't care
+//! which platform it's running on.
+
+#[cfg(unix)]
+mod unix;
+#[cfg(unix)]
+pub(crate) use self::unix::*;
+
+#[cfg(windows)]
+mod windows;
+#[cfg(windows)]
+pub(crate) use self::windows::*;
@@ -0,0 +1,63 @@
+use std::fs::{File, Metadata};
+use std::io::Result;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+use tempfile::Builder;
+
+use crate::write_owner_line;
+
+// Do the two Metadata reference the same file?
+fn same_file(a: &Metadata, b: &Metadata) -> bool {
+    a.dev() == b.dev() && a.ino() == b.ino()
+}
+
+// Makes a single attempt to acquire the lock using the hard-link
+// trick, which is atomic on every filesystem including NFS. Returns
+// `Ok(None)` if another process currently holds the lock.
+pub(crate) fn try_acquire(path: &Path, tempdir: &Path, pid_check: bool) -> Result<Option<File>> {
+    // Create a unique temporary file in the same directory
+    let temp = Builder::new().tempfile_in(tempdir)?;
+    if pid_check {
+        write_owner_line(temp.as_file())?;
+    }
+    let tempmeta = temp.as_file().metadata()?;
+    // link temporary file to destination, ignore the result
+    std::fs::hard_link(temp.path(), path).ok();
+    // Drop the temporary file
+    let temp = temp.into_file();
+    // stat the destination lock file
+    let destmeta = match std::fs::metadata(path) {
+        Ok(meta) => meta,
+        Err(_) => return Ok(None),
+    };
+    // Compare result of stat to temporary file
+    if same_file(&destmeta, &tempmeta) {
+        Ok(Some(temp))
+    } else {
+        Ok(None)
+    }
+}
+
+// The local machine's hostname, as reported by `gethostname(2)`.
+pub(crate) fn hostname() -> Option<String> {
+    let mut buf = vec![0u8; 256];
+    let ret = unsafe { libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len()) };
+    if ret != 0 {
+        return None;
+    }
+    let len = buf.iter().position(|&b| b == 0)?;
+    buf.truncate(len);
+    String::from_utf8(buf).ok()
+}
+
+// Is the process named by `pid` still alive? Uses the `kill(pid, 0)`
+// idiom: no signal is sent, but the error (if any) tells us whether the
+// process exists.
+pub(crate) fn process_alive(pid: i32) -> bool {
+    if unsafe { libc::kill(pid as libc::pid_t, 0) } == 0 {
+        true
+    } else {
+        std::io::Error::last_os_error().raw_os_error() != Some(libc::ESRCH)
+    }
+}
@@ -0,0 +1,37 @@
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Result};
+use std::path::Path;
+
+use crate::write_owner_line;
+
+// Makes a single attempt to acquire the lock. Windows has no
+// equivalent of the hard-link + inode trick used on Unix, so instead
+// this relies on `create_new`, which atomically fails if the file
+// already exists. Returns `Ok(None)` if another process currently
+// holds the lock.
+pub(crate) fn try_acquire(path: &Path, _tempdir: &Path, pid_check: bool) -> Result<Option<File>> {
+    match OpenOptions::new().read(true).write(true).create_new(true).open(path) {
+        Ok(file) => {
+            if pid_check {
+                write_owner_line(&file)?;
+            }
+            Ok(Some(file))
+        }
+        Err(ref e) if e.kind() == ErrorKind::AlreadyExists => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// Windows has no `HOSTNAME` equivalent in std; fall back to the
+// environment, which is set on every Windows install.
+pub(crate) fn hostname() -> Option<String> {
+    std::env::var("COMPUTERNAME").ok()
+}
+
+// There's no cheap, dependency-free way to probe process liveness by
+// PID on Windows, so treat every PID as alive. This just means
+// `pid_check` has no effect on this platform and lock files fall back
+// to the `stale_age` rule.
+pub(crate) fn process_alive(_pid: i32) -> bool {
+    true
+}